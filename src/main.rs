@@ -1,5 +1,7 @@
 use rand::prelude::*;
+use rand_pcg::Pcg64Mcg;
 use rayon::prelude::*;
+use std::hash::{Hash, Hasher};
 use std::sync::Arc;
 
 #[derive(Copy, Clone, Default, Debug)]
@@ -145,6 +147,7 @@ impl ::std::ops::Index<Axis> for Vec3 {
 struct Ray {
     origin: Vec3,
     direction: Vec3,
+    time: f32,
 }
 
 impl Ray {
@@ -158,11 +161,55 @@ pub struct HitRecord {
     t: f32,
     p: Vec3,
     normal: Vec3,
+    front_face: bool,
     material: Arc<dyn Material>,
 }
 
 trait Object: Sync + Send {
     fn hit(&self, ray: &Ray, t_range: std::ops::Range<f32>) -> Option<HitRecord>;
+    fn bounding_box(&self) -> Option<Aabb>;
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Aabb {
+    min: Vec3,
+    max: Vec3,
+}
+
+impl Aabb {
+    fn hit(&self, ray: &Ray, t_range: std::ops::Range<f32>) -> bool {
+        let mut t_min = t_range.start;
+        let mut t_max = t_range.end;
+        for &a in &[X, Y, Z] {
+            let inv_d = 1. / ray.direction[a];
+            let mut t0 = (self.min[a] - ray.origin[a]) * inv_d;
+            let mut t1 = (self.max[a] - ray.origin[a]) * inv_d;
+            if inv_d < 0. {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            t_min = t_min.max(t0);
+            t_max = t_max.min(t1);
+            if t_max <= t_min {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+fn surrounding_box(a: &Aabb, b: &Aabb) -> Aabb {
+    Aabb {
+        min: Vec3(
+            a.min.0.min(b.min.0),
+            a.min.1.min(b.min.1),
+            a.min.2.min(b.min.2),
+        ),
+        max: Vec3(
+            a.max.0.max(b.max.0),
+            a.max.1.max(b.max.1),
+            a.max.2.max(b.max.2),
+        ),
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -172,6 +219,71 @@ pub struct Sphere {
     material: Arc<dyn Material>,
 }
 
+#[derive(Clone, Debug)]
+pub struct MovingSphere {
+    center0: Vec3,
+    center1: Vec3,
+    time0: f32,
+    time1: f32,
+    radius: f32,
+    material: Arc<dyn Material>,
+}
+
+impl MovingSphere {
+    fn center(&self, time: f32) -> Vec3 {
+        self.center0
+            + ((time - self.time0) / (self.time1 - self.time0)) * (self.center1 - self.center0)
+    }
+}
+
+impl Object for MovingSphere {
+    fn hit(&self, ray: &Ray, t_range: std::ops::Range<f32>) -> Option<HitRecord> {
+        let center = self.center(ray.time);
+        let oc = ray.origin - center;
+        let a = ray.direction.dot(&ray.direction);
+        let b = oc.dot(&ray.direction);
+        let c = oc.dot(&oc) - self.radius * self.radius;
+        let discriminant = b * b - a * c;
+        if discriminant > 0. {
+            for &t in &[
+                (-b - discriminant.sqrt()) / a,
+                (-b + discriminant.sqrt()) / a,
+            ] {
+                if t < t_range.end && t >= t_range.start {
+                    let p = ray.point_at_parameter(t);
+                    let outward_normal = (p - center) / self.radius;
+                    let front_face = ray.direction.dot(&outward_normal) < 0.;
+                    return Some(HitRecord {
+                        t,
+                        p,
+                        normal: if front_face {
+                            outward_normal
+                        } else {
+                            -outward_normal
+                        },
+                        front_face,
+                        material: Arc::clone(&self.material),
+                    });
+                }
+            }
+        }
+        None
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let r = Vec3(self.radius.abs(), self.radius.abs(), self.radius.abs());
+        let box0 = Aabb {
+            min: self.center0 - r,
+            max: self.center0 + r,
+        };
+        let box1 = Aabb {
+            min: self.center1 - r,
+            max: self.center1 + r,
+        };
+        Some(surrounding_box(&box0, &box1))
+    }
+}
+
 impl Object for Sphere {
     fn hit(&self, ray: &Ray, t_range: std::ops::Range<f32>) -> Option<HitRecord> {
         let oc = ray.origin - self.center;
@@ -186,10 +298,17 @@ impl Object for Sphere {
             ] {
                 if t < t_range.end && t >= t_range.start {
                     let p = ray.point_at_parameter(t);
+                    let outward_normal = (p - self.center) / self.radius;
+                    let front_face = ray.direction.dot(&outward_normal) < 0.;
                     return Some(HitRecord {
                         t,
                         p,
-                        normal: (p - self.center) / self.radius,
+                        normal: if front_face {
+                            outward_normal
+                        } else {
+                            -outward_normal
+                        },
+                        front_face,
                         material: Arc::clone(&self.material),
                     });
                 }
@@ -197,6 +316,14 @@ impl Object for Sphere {
         }
         None
     }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let r = Vec3(self.radius.abs(), self.radius.abs(), self.radius.abs());
+        Some(Aabb {
+            min: self.center - r,
+            max: self.center + r,
+        })
+    }
 }
 
 impl<T> Object for [T]
@@ -214,15 +341,297 @@ where
             hit
         })
     }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let mut result: Option<Aabb> = None;
+        for obj in self.iter() {
+            let b = obj.bounding_box()?;
+            result = Some(match result {
+                Some(a) => surrounding_box(&a, &b),
+                None => b,
+            });
+        }
+        result
+    }
+}
+
+pub struct BvhNode {
+    left: Box<dyn Object>,
+    right: Box<dyn Object>,
+    bbox: Aabb,
+}
+
+impl BvhNode {
+    fn build(mut objects: Vec<Box<dyn Object>>, rng: &mut dyn RngCore) -> Box<dyn Object> {
+        let axis = [X, Y, Z][(rng.gen::<u32>() % 3) as usize];
+        objects.sort_by(|a, b| {
+            centroid(a.as_ref(), axis)
+                .partial_cmp(&centroid(b.as_ref(), axis))
+                .unwrap()
+        });
+
+        if objects.len() == 1 {
+            return objects.pop().unwrap();
+        }
+
+        let right = BvhNode::build(objects.split_off(objects.len() / 2), rng);
+        let left = BvhNode::build(objects, rng);
+        let bbox = surrounding_box(
+            &left.bounding_box().unwrap(),
+            &right.bounding_box().unwrap(),
+        );
+        Box::new(BvhNode { left, right, bbox })
+    }
+}
+
+fn centroid(obj: &dyn Object, axis: Axis) -> f32 {
+    let b = obj.bounding_box().unwrap();
+    (b.min[axis] + b.max[axis]) / 2.
+}
+
+impl Object for BvhNode {
+    fn hit(&self, ray: &Ray, t_range: std::ops::Range<f32>) -> Option<HitRecord> {
+        if !self.bbox.hit(ray, t_range.clone()) {
+            return None;
+        }
+        let hit_left = self.left.hit(ray, t_range.clone());
+        let end = hit_left.as_ref().map(|h| h.t).unwrap_or(t_range.end);
+        let hit_right = self.right.hit(ray, t_range.start..end);
+        hit_right.or(hit_left)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(self.bbox)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct XyRect {
+    x0: f32,
+    x1: f32,
+    y0: f32,
+    y1: f32,
+    k: f32,
+    material: Arc<dyn Material>,
+}
+
+impl Object for XyRect {
+    fn hit(&self, ray: &Ray, t_range: std::ops::Range<f32>) -> Option<HitRecord> {
+        let t = (self.k - ray.origin[Z]) / ray.direction[Z];
+        if t < t_range.start || t >= t_range.end {
+            return None;
+        }
+        let x = ray.origin[X] + t * ray.direction[X];
+        let y = ray.origin[Y] + t * ray.direction[Y];
+        if x < self.x0 || x > self.x1 || y < self.y0 || y > self.y1 {
+            return None;
+        }
+        let outward_normal = Vec3(0., 0., 1.);
+        let front_face = ray.direction.dot(&outward_normal) < 0.;
+        Some(HitRecord {
+            t,
+            p: ray.point_at_parameter(t),
+            normal: if front_face {
+                outward_normal
+            } else {
+                -outward_normal
+            },
+            front_face,
+            material: Arc::clone(&self.material),
+        })
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(Aabb {
+            min: Vec3(self.x0, self.y0, self.k - 0.0001),
+            max: Vec3(self.x1, self.y1, self.k + 0.0001),
+        })
+    }
 }
 
-fn color(world: &[Box<dyn Object>], mut ray: Ray) -> Vec3 {
+#[derive(Clone, Debug)]
+pub struct XzRect {
+    x0: f32,
+    x1: f32,
+    z0: f32,
+    z1: f32,
+    k: f32,
+    material: Arc<dyn Material>,
+}
+
+impl Object for XzRect {
+    fn hit(&self, ray: &Ray, t_range: std::ops::Range<f32>) -> Option<HitRecord> {
+        let t = (self.k - ray.origin[Y]) / ray.direction[Y];
+        if t < t_range.start || t >= t_range.end {
+            return None;
+        }
+        let x = ray.origin[X] + t * ray.direction[X];
+        let z = ray.origin[Z] + t * ray.direction[Z];
+        if x < self.x0 || x > self.x1 || z < self.z0 || z > self.z1 {
+            return None;
+        }
+        let outward_normal = Vec3(0., 1., 0.);
+        let front_face = ray.direction.dot(&outward_normal) < 0.;
+        Some(HitRecord {
+            t,
+            p: ray.point_at_parameter(t),
+            normal: if front_face {
+                outward_normal
+            } else {
+                -outward_normal
+            },
+            front_face,
+            material: Arc::clone(&self.material),
+        })
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(Aabb {
+            min: Vec3(self.x0, self.k - 0.0001, self.z0),
+            max: Vec3(self.x1, self.k + 0.0001, self.z1),
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct YzRect {
+    y0: f32,
+    y1: f32,
+    z0: f32,
+    z1: f32,
+    k: f32,
+    material: Arc<dyn Material>,
+}
+
+impl Object for YzRect {
+    fn hit(&self, ray: &Ray, t_range: std::ops::Range<f32>) -> Option<HitRecord> {
+        let t = (self.k - ray.origin[X]) / ray.direction[X];
+        if t < t_range.start || t >= t_range.end {
+            return None;
+        }
+        let y = ray.origin[Y] + t * ray.direction[Y];
+        let z = ray.origin[Z] + t * ray.direction[Z];
+        if y < self.y0 || y > self.y1 || z < self.z0 || z > self.z1 {
+            return None;
+        }
+        let outward_normal = Vec3(1., 0., 0.);
+        let front_face = ray.direction.dot(&outward_normal) < 0.;
+        Some(HitRecord {
+            t,
+            p: ray.point_at_parameter(t),
+            normal: if front_face {
+                outward_normal
+            } else {
+                -outward_normal
+            },
+            front_face,
+            material: Arc::clone(&self.material),
+        })
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(Aabb {
+            min: Vec3(self.k - 0.0001, self.y0, self.z0),
+            max: Vec3(self.k + 0.0001, self.y1, self.z1),
+        })
+    }
+}
+
+pub struct BoxObj {
+    p0: Vec3,
+    p1: Vec3,
+    sides: Vec<Box<dyn Object>>,
+}
+
+impl BoxObj {
+    fn new(p0: Vec3, p1: Vec3, material: Arc<dyn Material>) -> BoxObj {
+        let sides: Vec<Box<dyn Object>> = vec![
+            Box::new(XyRect {
+                x0: p0[X],
+                x1: p1[X],
+                y0: p0[Y],
+                y1: p1[Y],
+                k: p1[Z],
+                material: Arc::clone(&material),
+            }),
+            Box::new(XyRect {
+                x0: p0[X],
+                x1: p1[X],
+                y0: p0[Y],
+                y1: p1[Y],
+                k: p0[Z],
+                material: Arc::clone(&material),
+            }),
+            Box::new(XzRect {
+                x0: p0[X],
+                x1: p1[X],
+                z0: p0[Z],
+                z1: p1[Z],
+                k: p1[Y],
+                material: Arc::clone(&material),
+            }),
+            Box::new(XzRect {
+                x0: p0[X],
+                x1: p1[X],
+                z0: p0[Z],
+                z1: p1[Z],
+                k: p0[Y],
+                material: Arc::clone(&material),
+            }),
+            Box::new(YzRect {
+                y0: p0[Y],
+                y1: p1[Y],
+                z0: p0[Z],
+                z1: p1[Z],
+                k: p1[X],
+                material: Arc::clone(&material),
+            }),
+            Box::new(YzRect {
+                y0: p0[Y],
+                y1: p1[Y],
+                z0: p0[Z],
+                z1: p1[Z],
+                k: p0[X],
+                material: Arc::clone(&material),
+            }),
+        ];
+        BoxObj { p0, p1, sides }
+    }
+}
+
+impl Object for BoxObj {
+    fn hit(&self, ray: &Ray, t_range: std::ops::Range<f32>) -> Option<HitRecord> {
+        self.sides.hit(ray, t_range)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(Aabb {
+            min: self.p0,
+            max: self.p1,
+        })
+    }
+}
+
+fn color(
+    world: &dyn Object,
+    mut ray: Ray,
+    background: Option<Vec3>,
+    rng: &mut dyn RngCore,
+) -> Vec3 {
     let mut strength = Vec3(1., 1., 1.);
+    let mut accum = Vec3::default();
     let mut bounces = 0;
 
     while let Some(hit) = world.hit(&ray, 0.001..std::f32::MAX) {
+        let emitted = hit.material.emitted();
+        accum = accum
+            + Vec3(
+                strength.0 * emitted.0,
+                strength.1 * emitted.1,
+                strength.2 * emitted.2,
+            );
         if bounces < 50 {
-            if let Some((new_ray, attenuation)) = hit.material.scatter(&ray, &hit) {
+            if let Some((new_ray, attenuation)) = hit.material.scatter(&ray, &hit, rng) {
                 ray = new_ray;
                 strength = Vec3(
                     strength.0 * attenuation.0,
@@ -233,13 +642,20 @@ fn color(world: &[Box<dyn Object>], mut ray: Ray) -> Vec3 {
                 continue;
             }
         }
-        return Vec3::default();
+        return accum;
     }
 
-    let unit_direction = ray.direction.into_unit();
-    let t = 0.5 * (unit_direction[Y] + 1.0);
-    let col = (1. - t) * Vec3(1., 1., 1.) + t * Vec3(0.5, 0.7, 1.0);
-    Vec3(strength.0 * col.0, strength.1 * col.1, strength.2 * col.2)
+    let background = background.unwrap_or_else(|| {
+        let unit_direction = ray.direction.into_unit();
+        let t = 0.5 * (unit_direction[Y] + 1.0);
+        (1. - t) * Vec3(1., 1., 1.) + t * Vec3(0.5, 0.7, 1.0)
+    });
+    accum
+        + Vec3(
+            strength.0 * background.0,
+            strength.1 * background.1,
+            strength.2 * background.2,
+        )
 }
 
 struct Camera {
@@ -250,18 +666,23 @@ struct Camera {
     u: Vec3,
     v: Vec3,
     lens_radius: f32,
+    time0: f32,
+    time1: f32,
+}
+
+struct Lens {
+    aperture: f32,
+    focus_dist: f32,
+    shutter: std::ops::Range<f32>,
 }
 
 impl Camera {
-    fn look(
-        look_from: Vec3,
-        look_at: Vec3,
-        up: Vec3,
-        fov: f32,
-        aspect: f32,
-        aperture: f32,
-        focus_dist: f32,
-    ) -> Self {
+    fn look(look_from: Vec3, look_at: Vec3, up: Vec3, fov: f32, aspect: f32, lens: Lens) -> Self {
+        let Lens {
+            aperture,
+            focus_dist,
+            shutter,
+        } = lens;
         let lens_radius = aperture / 2.;
         let theta = fov * std::f32::consts::PI / 180.;
         let half_height = f32::tan(theta / 2.);
@@ -282,23 +703,26 @@ impl Camera {
             u,
             v,
             lens_radius,
+            time0: shutter.start,
+            time1: shutter.end,
         }
     }
 
-    fn get_ray(&self, s: f32, t: f32) -> Ray {
-        let rd = self.lens_radius * in_unit_disc();
+    fn get_ray(&self, s: f32, t: f32, rng: &mut dyn RngCore) -> Ray {
+        let rd = self.lens_radius * in_unit_disc(rng);
         let offset = rd[X] * self.u + rd[Y] * self.v;
+        let time = self.time0 + rng.gen::<f32>() * (self.time1 - self.time0);
         Ray {
             origin: self.origin + offset,
             direction: self.lower_left_corner + s * self.horizontal + t * self.vertical
                 - self.origin
                 - offset,
+            time,
         }
     }
 }
 
-fn in_unit_sphere() -> Vec3 {
-    let mut rng = rand::thread_rng();
+fn in_unit_sphere(rng: &mut dyn RngCore) -> Vec3 {
     loop {
         let v = 2. * Vec3(rng.gen(), rng.gen(), rng.gen()) - Vec3(1., 1., 1.);
         if v.dot(&v) < 1. {
@@ -307,8 +731,7 @@ fn in_unit_sphere() -> Vec3 {
     }
 }
 
-fn in_unit_disc() -> Vec3 {
-    let mut rng = rand::thread_rng();
+fn in_unit_disc(rng: &mut dyn RngCore) -> Vec3 {
     loop {
         let v = 2. * Vec3(rng.gen(), rng.gen(), 0.) - Vec3(1., 1., 0.);
         if v.dot(&v) < 1. {
@@ -318,7 +741,11 @@ fn in_unit_disc() -> Vec3 {
 }
 
 trait Material: std::fmt::Debug + Sync + Send {
-    fn scatter(&self, ray: &Ray, hit: &HitRecord) -> Option<(Ray, Vec3)>;
+    fn scatter(&self, ray: &Ray, hit: &HitRecord, rng: &mut dyn RngCore) -> Option<(Ray, Vec3)>;
+
+    fn emitted(&self) -> Vec3 {
+        Vec3::default()
+    }
 }
 
 #[derive(Debug)]
@@ -327,11 +754,12 @@ struct Lambertian {
 }
 
 impl Material for Lambertian {
-    fn scatter(&self, _ray: &Ray, hit: &HitRecord) -> Option<(Ray, Vec3)> {
-        let target = hit.p + hit.normal + in_unit_sphere();
+    fn scatter(&self, ray: &Ray, hit: &HitRecord, rng: &mut dyn RngCore) -> Option<(Ray, Vec3)> {
+        let target = hit.p + hit.normal + in_unit_sphere(rng);
         let scattered = Ray {
             origin: hit.p,
             direction: target - hit.p,
+            time: ray.time,
         };
         Some((scattered, self.albedo))
     }
@@ -344,11 +772,12 @@ struct Metal {
 }
 
 impl Material for Metal {
-    fn scatter(&self, ray: &Ray, hit: &HitRecord) -> Option<(Ray, Vec3)> {
+    fn scatter(&self, ray: &Ray, hit: &HitRecord, rng: &mut dyn RngCore) -> Option<(Ray, Vec3)> {
         let scattered = Ray {
             origin: hit.p,
             direction: reflect(&ray.direction.into_unit(), &hit.normal)
-                + self.fuzz * in_unit_sphere(),
+                + self.fuzz * in_unit_sphere(rng),
+            time: ray.time,
         };
         if scattered.direction.dot(&hit.normal) > 0. {
             Some((scattered, self.albedo))
@@ -370,35 +799,49 @@ struct Dielectric {
 }
 
 impl Material for Dielectric {
-    fn scatter(&self, ray: &Ray, hit: &HitRecord) -> Option<(Ray, Vec3)> {
-        let (outward_normal, ni_over_nt, cosine) = if ray.direction.dot(&hit.normal) > 0. {
-            (
-                -hit.normal,
-                self.ref_idx,
-                self.ref_idx * ray.direction.dot(&hit.normal) / ray.direction.length(),
-            )
+    fn scatter(&self, ray: &Ray, hit: &HitRecord, rng: &mut dyn RngCore) -> Option<(Ray, Vec3)> {
+        let ni_over_nt = if hit.front_face {
+            1.0 / self.ref_idx
+        } else {
+            self.ref_idx
+        };
+        let cos = -ray.direction.dot(&hit.normal) / ray.direction.length();
+        let cosine = if hit.front_face {
+            cos
         } else {
-            (
-                hit.normal,
-                1.0 / self.ref_idx,
-                -ray.direction.dot(&hit.normal) / ray.direction.length(),
-            )
+            self.ref_idx * cos
         };
 
-        let direction = refract(&ray.direction, &outward_normal, ni_over_nt)
-            .filter(|_| rand::thread_rng().gen::<f32>() >= schlick(cosine, self.ref_idx))
+        let direction = refract(&ray.direction, &hit.normal, ni_over_nt)
+            .filter(|_| rng.gen::<f32>() >= schlick(cosine, self.ref_idx))
             .unwrap_or_else(|| reflect(&ray.direction, &hit.normal));
 
         let attenuation = Vec3(1.0, 1.0, 1.0);
-        let ray = Ray {
+        let scattered = Ray {
             origin: hit.p,
             direction,
+            time: ray.time,
         };
-        Some((ray, attenuation))
+        Some((scattered, attenuation))
     }
 }
 
-fn random_scene() -> Vec<Box<dyn Object>> {
+#[derive(Debug)]
+struct DiffuseLight {
+    emit: Vec3,
+}
+
+impl Material for DiffuseLight {
+    fn scatter(&self, _ray: &Ray, _hit: &HitRecord, _rng: &mut dyn RngCore) -> Option<(Ray, Vec3)> {
+        None
+    }
+
+    fn emitted(&self) -> Vec3 {
+        self.emit
+    }
+}
+
+fn random_scene(rng: &mut dyn RngCore) -> Vec<Box<dyn Object>> {
     let mut world: Vec<Box<dyn Object>> = vec![Box::new(Sphere {
         center: Vec3(0., -1000., 0.),
         radius: 1000.,
@@ -407,8 +850,6 @@ fn random_scene() -> Vec<Box<dyn Object>> {
         }),
     })];
 
-    let mut rng = rand::thread_rng();
-
     for a in -11..11 {
         for b in -11..11 {
             let center = Vec3(
@@ -419,9 +860,12 @@ fn random_scene() -> Vec<Box<dyn Object>> {
             if (center - Vec3(4., 0.2, 0.)).length() > 0.9 {
                 let choose_mat = rng.gen::<f32>();
 
-                let obj = if choose_mat < 0.8 {
-                    Box::new(Sphere {
-                        center,
+                let obj: Box<dyn Object> = if choose_mat < 0.8 {
+                    Box::new(MovingSphere {
+                        center0: center,
+                        center1: center + Vec3(0., 0.5 * rng.gen::<f32>(), 0.),
+                        time0: 0.,
+                        time1: 1.,
                         radius: 0.2,
                         material: Arc::new(Lambertian {
                             albedo: Vec3(
@@ -482,6 +926,109 @@ fn random_scene() -> Vec<Box<dyn Object>> {
     world
 }
 
+fn simple_light() -> Vec<Box<dyn Object>> {
+    vec![
+        Box::new(Sphere {
+            center: Vec3(0., -1000., 0.),
+            radius: 1000.,
+            material: Arc::new(Lambertian {
+                albedo: Vec3(0.5, 0.5, 0.5),
+            }),
+        }),
+        Box::new(Sphere {
+            center: Vec3(0., 2., 0.),
+            radius: 2.,
+            material: Arc::new(Lambertian {
+                albedo: Vec3(0.4, 0.2, 0.1),
+            }),
+        }),
+        Box::new(Sphere {
+            center: Vec3(0., 7., 0.),
+            radius: 2.,
+            material: Arc::new(DiffuseLight {
+                emit: Vec3(4., 4., 4.),
+            }),
+        }),
+    ]
+}
+
+fn cornell_box() -> Vec<Box<dyn Object>> {
+    let red: Arc<dyn Material> = Arc::new(Lambertian {
+        albedo: Vec3(0.65, 0.05, 0.05),
+    });
+    let white: Arc<dyn Material> = Arc::new(Lambertian {
+        albedo: Vec3(0.73, 0.73, 0.73),
+    });
+    let green: Arc<dyn Material> = Arc::new(Lambertian {
+        albedo: Vec3(0.12, 0.45, 0.15),
+    });
+    let light: Arc<dyn Material> = Arc::new(DiffuseLight {
+        emit: Vec3(15., 15., 15.),
+    });
+
+    let world: Vec<Box<dyn Object>> = vec![
+        Box::new(YzRect {
+            y0: 0.,
+            y1: 555.,
+            z0: 0.,
+            z1: 555.,
+            k: 555.,
+            material: green,
+        }),
+        Box::new(YzRect {
+            y0: 0.,
+            y1: 555.,
+            z0: 0.,
+            z1: 555.,
+            k: 0.,
+            material: red,
+        }),
+        Box::new(XzRect {
+            x0: 213.,
+            x1: 343.,
+            z0: 227.,
+            z1: 332.,
+            k: 554.,
+            material: light,
+        }),
+        Box::new(XzRect {
+            x0: 0.,
+            x1: 555.,
+            z0: 0.,
+            z1: 555.,
+            k: 0.,
+            material: Arc::clone(&white),
+        }),
+        Box::new(XzRect {
+            x0: 0.,
+            x1: 555.,
+            z0: 0.,
+            z1: 555.,
+            k: 555.,
+            material: Arc::clone(&white),
+        }),
+        Box::new(XyRect {
+            x0: 0.,
+            x1: 555.,
+            y0: 0.,
+            y1: 555.,
+            k: 555.,
+            material: Arc::clone(&white),
+        }),
+        Box::new(BoxObj::new(
+            Vec3(130., 0., 65.),
+            Vec3(295., 165., 230.),
+            Arc::clone(&white),
+        )),
+        Box::new(BoxObj::new(
+            Vec3(265., 0., 295.),
+            Vec3(430., 330., 460.),
+            Arc::clone(&white),
+        )),
+    ];
+    world
+}
+
 struct Image(Vec<Vec<Vec3>>);
 
 impl Image {
@@ -496,11 +1043,23 @@ impl Image {
     }
 }
 
+fn pixel_seed(x: usize, y: usize, base: u64) -> u64 {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    base.hash(&mut hasher);
+    x.hash(&mut hasher);
+    y.hash(&mut hasher);
+    hasher.finish()
+}
+
 fn print_ppm(image: Image) {
     println!("P3\n{} {}\n255", image.0[0].len(), image.0.len());
     for scanline in image.0 {
         for col in scanline {
-            let col = Vec3(col.0.sqrt(), col.1.sqrt(), col.2.sqrt());
+            let col = Vec3(
+                col.0.clamp(0., 1.).sqrt(),
+                col.1.clamp(0., 1.).sqrt(),
+                col.2.clamp(0., 1.).sqrt(),
+            );
 
             let ir = (255.99 * col[R]) as i32;
             let ig = (255.99 * col[G]) as i32;
@@ -516,31 +1075,70 @@ fn main() {
     const NY: usize = 100;
     const NS: usize = 50;
 
-    let world = random_scene();
-
-    let look_from = Vec3(13., 2., 3.);
-    let look_at = Vec3(0., 0., 0.);
+    let base_seed: u64 = std::env::args()
+        .skip_while(|a| a != "--seed")
+        .nth(1)
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(0);
+
+    let scene = std::env::args()
+        .skip_while(|a| a != "--scene")
+        .nth(1)
+        .unwrap_or_else(|| "random".to_string());
+
+    let mut scene_rng = Pcg64Mcg::seed_from_u64(base_seed);
+
+    let (objects, look_from, look_at, fov, aperture, background) = match scene.as_str() {
+        "cornell" => (
+            cornell_box(),
+            Vec3(278., 278., -800.),
+            Vec3(278., 278., 0.),
+            40.,
+            0.,
+            Some(Vec3::default()),
+        ),
+        "light" => (
+            simple_light(),
+            Vec3(26., 3., 6.),
+            Vec3(0., 2., 0.),
+            20.,
+            0.,
+            Some(Vec3::default()),
+        ),
+        _ => (
+            random_scene(&mut scene_rng),
+            Vec3(13., 2., 3.),
+            Vec3(0., 0., 0.),
+            20.,
+            0.1,
+            None,
+        ),
+    };
+
+    let world = BvhNode::build(objects, &mut scene_rng);
     let dist_to_focus = 10.;
-    let aperture = 0.1;
 
     let camera = Camera::look(
         look_from,
         look_at,
         Vec3(0., 1., 0.),
-        20.,
+        fov,
         NX as f32 / NY as f32,
-        aperture,
-        dist_to_focus,
+        Lens {
+            aperture,
+            focus_dist: dist_to_focus,
+            shutter: 0. ..1.,
+        },
     );
 
     let image = Image::compute(NX, NY, |x, y| {
+        let mut rng = Pcg64Mcg::seed_from_u64(pixel_seed(x, y, base_seed));
         let col: Vec3 = (0..NS)
             .map(|_| {
-                let mut rng = rand::thread_rng();
                 let u = (x as f32 + rng.gen::<f32>()) / NX as f32;
                 let v = (y as f32 + rng.gen::<f32>()) / NY as f32;
-                let r = camera.get_ray(u, v);
-                color(&world, r)
+                let r = camera.get_ray(u, v, &mut rng);
+                color(world.as_ref(), r, background, &mut rng)
             })
             .sum();
         col / NS as f32